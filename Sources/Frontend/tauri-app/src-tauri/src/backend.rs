@@ -0,0 +1,366 @@
+// Adaptors that let the desktop app talk to different inference backends
+// instead of hardcoding the bundled Python shim. Each `Backend` impl knows
+// how to shape a request for its provider and how to pull tags back out
+// of that provider's response.
+
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    PythonShim,
+    OllamaDirect,
+    OpenAiCompatible,
+    TgiGenerate,
+}
+
+// Where to reach the backend and how to authenticate with it.
+#[derive(Clone, Deserialize)]
+pub struct BackendConfig {
+    pub base_url: String,
+    pub auth_token: Option<String>,
+    pub kind: BackendKind,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            base_url: "http://127.0.0.1:5000".to_string(),
+            auth_token: None,
+            kind: BackendKind::PythonShim,
+        }
+    }
+}
+
+// Attach an optional bearer token for hosted endpoints. The bundled Python
+// shim runs unauthenticated on localhost, so most configs leave this empty.
+pub fn build_headers(config: &BackendConfig) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+
+    if let Some(token) = &config.auth_token {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token)) {
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+    }
+
+    headers
+}
+
+pub struct TagRequest {
+    pub file_path: String,
+    pub context: Option<String>,
+    pub parameters: Option<serde_json::Value>,
+}
+
+pub struct TagResult {
+    pub success: bool,
+    pub tags: Vec<String>,
+    pub error: Option<String>,
+    pub file_type: Option<String>,
+    pub model_used: Option<String>,
+}
+
+fn prompt_for(request: &TagRequest) -> String {
+    match &request.context {
+        Some(context) if !context.trim().is_empty() => format!(
+            "Generate a short list of descriptive tags for the file at {}. Context: {}",
+            request.file_path, context
+        ),
+        _ => format!(
+            "Generate a short list of descriptive tags for the file at {}.",
+            request.file_path
+        ),
+    }
+}
+
+fn tags_from_text(text: &str) -> Vec<String> {
+    text.split([',', '\n'])
+        .map(|tag| tag.trim().trim_start_matches('-').trim())
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect()
+}
+
+fn guess_mime_type(path: &str) -> &'static str {
+    match std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+        .as_str()
+    {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+// Reads the file and base64-encodes it as a `data:` URI so direct-backend
+// adaptors can actually show the model the image instead of just its path.
+// Returns `None` for unreadable files (the adaptor falls back to a
+// text-only prompt, which the model will tag poorly but won't crash on).
+fn image_data_uri(file_path: &str) -> Option<String> {
+    let bytes = std::fs::read(file_path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        guess_mime_type(file_path),
+        base64::encode(&bytes)
+    ))
+}
+
+fn model_from(request: &TagRequest, default: &str) -> String {
+    request
+        .parameters
+        .as_ref()
+        .and_then(|p| p.get("model"))
+        .and_then(|m| m.as_str())
+        .unwrap_or(default)
+        .to_string()
+}
+
+// The sampling knobs from `GenerationParams::to_json`, pulled out in one
+// place so each adaptor can re-shape them into its provider's expected
+// field names instead of forwarding the map as-is.
+struct SamplingFields {
+    temperature: Option<f64>,
+    top_p: Option<f64>,
+    max_tokens: Option<u64>,
+    stop: Option<Vec<String>>,
+}
+
+fn sampling_fields(request: &TagRequest) -> SamplingFields {
+    let params = request.parameters.as_ref();
+    SamplingFields {
+        temperature: params.and_then(|p| p.get("temperature")).and_then(|v| v.as_f64()),
+        top_p: params.and_then(|p| p.get("top_p")).and_then(|v| v.as_f64()),
+        max_tokens: params.and_then(|p| p.get("max_new_tokens")).and_then(|v| v.as_u64()),
+        stop: params
+            .and_then(|p| p.get("stop_tokens"))
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|s| s.as_str().map(|s| s.to_string())).collect()),
+    }
+}
+
+// Knows how to build a request body for its provider and parse tags back
+// out of that provider's response shape.
+pub trait Backend: Send + Sync {
+    fn endpoint(&self, config: &BackendConfig) -> String;
+    fn build_body(&self, request: &TagRequest) -> serde_json::Value;
+    fn parse_tags(&self, json: &serde_json::Value) -> TagResult;
+}
+
+// The bundled Python shim (the default, local backend).
+pub struct PythonShim;
+
+impl Backend for PythonShim {
+    fn endpoint(&self, config: &BackendConfig) -> String {
+        format!("{}/api/process-file", config.base_url)
+    }
+
+    fn build_body(&self, request: &TagRequest) -> serde_json::Value {
+        let mut body = serde_json::json!({ "file_path": request.file_path });
+
+        if let Some(context) = &request.context {
+            if !context.trim().is_empty() {
+                body["context"] = serde_json::Value::String(context.clone());
+            }
+        }
+        if let Some(parameters) = &request.parameters {
+            body["parameters"] = parameters.clone();
+        }
+
+        body
+    }
+
+    fn parse_tags(&self, json: &serde_json::Value) -> TagResult {
+        TagResult {
+            success: json.get("success").and_then(|v| v.as_bool()).unwrap_or(false),
+            tags: json
+                .get("tags")
+                .and_then(|t| t.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            error: json.get("error").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            file_type: json.get("file_type").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            model_used: json.get("model_used").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        }
+    }
+}
+
+// Talks directly to an Ollama server's `/api/generate` endpoint.
+pub struct OllamaDirect;
+
+impl Backend for OllamaDirect {
+    fn endpoint(&self, config: &BackendConfig) -> String {
+        format!("{}/api/generate", config.base_url)
+    }
+
+    fn build_body(&self, request: &TagRequest) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "model": model_from(request, "llava"),
+            "prompt": prompt_for(request),
+            "stream": false,
+        });
+
+        // Ollama's `images` field wants raw base64, not a `data:` URI.
+        if let Some(data_uri) = image_data_uri(&request.file_path) {
+            if let Some(b64) = data_uri.split(',').nth(1) {
+                body["images"] = serde_json::json!([b64]);
+            }
+        }
+
+        // Ollama takes sampling knobs under a nested `options` object.
+        let fields = sampling_fields(request);
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = fields.temperature {
+            options.insert("temperature".to_string(), serde_json::json!(temperature));
+        }
+        if let Some(top_p) = fields.top_p {
+            options.insert("top_p".to_string(), serde_json::json!(top_p));
+        }
+        if let Some(max_tokens) = fields.max_tokens {
+            options.insert("num_predict".to_string(), serde_json::json!(max_tokens));
+        }
+        if let Some(stop) = fields.stop {
+            options.insert("stop".to_string(), serde_json::json!(stop));
+        }
+        if !options.is_empty() {
+            body["options"] = serde_json::Value::Object(options);
+        }
+
+        body
+    }
+
+    fn parse_tags(&self, json: &serde_json::Value) -> TagResult {
+        let text = json.get("response").and_then(|v| v.as_str()).unwrap_or("");
+
+        TagResult {
+            success: !text.is_empty(),
+            tags: tags_from_text(text),
+            error: None,
+            file_type: None,
+            model_used: json.get("model").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        }
+    }
+}
+
+// Talks to any OpenAI-compatible `/chat/completions` endpoint.
+pub struct OpenAiCompatible;
+
+impl Backend for OpenAiCompatible {
+    fn endpoint(&self, config: &BackendConfig) -> String {
+        format!("{}/chat/completions", config.base_url)
+    }
+
+    fn build_body(&self, request: &TagRequest) -> serde_json::Value {
+        let mut content = vec![serde_json::json!({ "type": "text", "text": prompt_for(request) })];
+
+        if let Some(data_uri) = image_data_uri(&request.file_path) {
+            content.push(serde_json::json!({ "type": "image_url", "image_url": { "url": data_uri } }));
+        }
+
+        let mut body = serde_json::json!({
+            "model": model_from(request, "gpt-4o-mini"),
+            "messages": [{ "role": "user", "content": content }],
+        });
+
+        // OpenAI-compatible chat completions take sampling knobs as
+        // top-level fields rather than a nested object.
+        let fields = sampling_fields(request);
+        if let Some(temperature) = fields.temperature {
+            body["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_p) = fields.top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(max_tokens) = fields.max_tokens {
+            body["max_tokens"] = serde_json::json!(max_tokens);
+        }
+        if let Some(stop) = fields.stop {
+            body["stop"] = serde_json::json!(stop);
+        }
+
+        body
+    }
+
+    fn parse_tags(&self, json: &serde_json::Value) -> TagResult {
+        let text = json
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|choice| choice.get("message"))
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_str())
+            .unwrap_or("");
+
+        TagResult {
+            success: !text.is_empty(),
+            tags: tags_from_text(text),
+            error: None,
+            file_type: None,
+            model_used: json.get("model").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        }
+    }
+}
+
+// Talks to a Hugging Face Text Generation Inference server's `/generate`.
+pub struct TgiGenerate;
+
+impl Backend for TgiGenerate {
+    fn endpoint(&self, config: &BackendConfig) -> String {
+        format!("{}/generate", config.base_url)
+    }
+
+    fn build_body(&self, request: &TagRequest) -> serde_json::Value {
+        // TGI's vision-capable deployments (idefics, llava, etc.) take the
+        // image as inline markdown in the prompt rather than a separate
+        // field; a text-only TGI deployment will just ignore/reject it.
+        let prompt = match image_data_uri(&request.file_path) {
+            Some(data_uri) => format!("![]({})\n{}", data_uri, prompt_for(request)),
+            None => prompt_for(request),
+        };
+
+        // `model` doesn't apply here (TGI selects its model at deploy
+        // time, not per-request) and TGI's generation parameters use
+        // `stop`, not `stop_tokens`, so remap before forwarding.
+        let mut parameters = request.parameters.clone().unwrap_or_default();
+        if let serde_json::Value::Object(ref mut map) = parameters {
+            map.remove("model");
+            if let Some(stop_tokens) = map.remove("stop_tokens") {
+                map.insert("stop".to_string(), stop_tokens);
+            }
+        }
+
+        serde_json::json!({
+            "inputs": prompt,
+            "parameters": parameters,
+        })
+    }
+
+    fn parse_tags(&self, json: &serde_json::Value) -> TagResult {
+        let text = json.get("generated_text").and_then(|v| v.as_str()).unwrap_or("");
+
+        TagResult {
+            success: !text.is_empty(),
+            tags: tags_from_text(text),
+            error: None,
+            file_type: None,
+            model_used: None,
+        }
+    }
+}
+
+pub fn make_backend(kind: BackendKind) -> Box<dyn Backend> {
+    match kind {
+        BackendKind::PythonShim => Box::new(PythonShim),
+        BackendKind::OllamaDirect => Box::new(OllamaDirect),
+        BackendKind::OpenAiCompatible => Box::new(OpenAiCompatible),
+        BackendKind::TgiGenerate => Box::new(TgiGenerate),
+    }
+}
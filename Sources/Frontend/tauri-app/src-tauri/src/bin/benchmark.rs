@@ -0,0 +1,189 @@
+// Benchmark harness for the tagging pipeline. Loads one or more workload
+// files, drives the same HTTP endpoint as `process_file_for_tags`, and
+// writes a latency/throughput report so model and release regressions can
+// be caught instead of eyeballed.
+//
+// Usage: benchmark <workload.json> [more-workloads.json ...] [--output report.json]
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+const PROCESS_FILE_URL: &str = "http://127.0.0.1:5000/api/process-file";
+const DEFAULT_REPORT_PATH: &str = "bench_output.json";
+
+#[derive(Deserialize)]
+struct WorkloadFile {
+    path: String,
+    context: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Workload {
+    name: String,
+    files: Vec<WorkloadFile>,
+    model: String,
+    runs: u32,
+}
+
+#[derive(Serialize)]
+struct RunResult {
+    file_path: String,
+    run: u32,
+    success: bool,
+    elapsed_ms: u128,
+}
+
+#[derive(Serialize)]
+struct WorkloadReport {
+    workload: String,
+    model: String,
+    total_runs: usize,
+    success_rate: f64,
+    p50_ms: u128,
+    p95_ms: u128,
+    mean_ms: f64,
+    runs: Vec<RunResult>,
+}
+
+#[derive(Serialize)]
+struct Environment {
+    os: String,
+    arch: String,
+    cpu_count: usize,
+    app_version: String,
+}
+
+#[derive(Serialize)]
+struct BenchmarkReport {
+    environment: Environment,
+    workloads: Vec<WorkloadReport>,
+}
+
+fn percentile(sorted_ms: &[u128], pct: f64) -> u128 {
+    if sorted_ms.is_empty() {
+        return 0;
+    }
+    let rank = ((sorted_ms.len() as f64 - 1.0) * pct).round() as usize;
+    sorted_ms[rank]
+}
+
+async fn run_workload(client: &reqwest::Client, workload: &Workload) -> WorkloadReport {
+    let mut runs = Vec::new();
+
+    for file in &workload.files {
+        for run in 0..workload.runs {
+            let payload = serde_json::json!({
+                "file_path": file.path,
+                "context": file.context,
+                "parameters": { "model": workload.model },
+            });
+
+            let start = Instant::now();
+            let success = client
+                .post(PROCESS_FILE_URL)
+                .json(&payload)
+                .timeout(Duration::from_secs(360))
+                .send()
+                .await
+                .map(|r| r.status().is_success())
+                .unwrap_or(false);
+            let elapsed_ms = start.elapsed().as_millis();
+
+            runs.push(RunResult {
+                file_path: file.path.clone(),
+                run,
+                success,
+                elapsed_ms,
+            });
+        }
+    }
+
+    let mut durations: Vec<u128> = runs.iter().map(|r| r.elapsed_ms).collect();
+    durations.sort_unstable();
+    let successes = runs.iter().filter(|r| r.success).count();
+    let mean_ms = if durations.is_empty() {
+        0.0
+    } else {
+        durations.iter().sum::<u128>() as f64 / durations.len() as f64
+    };
+
+    WorkloadReport {
+        workload: workload.name.clone(),
+        model: workload.model.clone(),
+        total_runs: runs.len(),
+        success_rate: if runs.is_empty() {
+            0.0
+        } else {
+            successes as f64 / runs.len() as f64
+        },
+        p50_ms: percentile(&durations, 0.50),
+        p95_ms: percentile(&durations, 0.95),
+        mean_ms,
+        runs,
+    }
+}
+
+// Splits `--output <path>` (or `-o <path>`) out of the CLI args, returning
+// the remaining workload paths and the report path to write (defaulting to
+// `DEFAULT_REPORT_PATH` if the flag wasn't given).
+fn parse_args(args: Vec<String>) -> (Vec<String>, String) {
+    let mut workload_paths = Vec::new();
+    let mut output_path = DEFAULT_REPORT_PATH.to_string();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--output" | "-o" => {
+                output_path = iter
+                    .next()
+                    .unwrap_or_else(|| panic!("{} requires a path argument", arg));
+            }
+            _ => workload_paths.push(arg),
+        }
+    }
+
+    (workload_paths, output_path)
+}
+
+#[tokio::main]
+async fn main() {
+    let (workload_paths, output_path) = parse_args(std::env::args().skip(1).collect());
+    if workload_paths.is_empty() {
+        eprintln!("usage: benchmark <workload.json> [more-workloads.json ...] [--output report.json]");
+        std::process::exit(1);
+    }
+
+    let client = reqwest::Client::new();
+    let mut workload_reports = Vec::new();
+
+    for path in &workload_paths {
+        let contents = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("failed to read workload {}: {}", path, e));
+        let workload: Workload = serde_json::from_str(&contents)
+            .unwrap_or_else(|e| panic!("failed to parse workload {}: {}", path, e));
+
+        eprintln!(
+            "running workload '{}' ({} files x {} runs)...",
+            workload.name,
+            workload.files.len(),
+            workload.runs
+        );
+        workload_reports.push(run_workload(&client, &workload).await);
+    }
+
+    let report = BenchmarkReport {
+        environment: Environment {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpu_count: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        workloads: workload_reports,
+    };
+
+    let json = serde_json::to_string_pretty(&report).expect("failed to serialize report");
+    std::fs::write(&output_path, &json).expect("failed to write report");
+    println!("wrote benchmark report to {}", output_path);
+}
@@ -0,0 +1,131 @@
+// Compact BlurHash placeholders so the folder grid can paint a blurred
+// gradient before real thumbnails have loaded. Implements the scheme
+// described at https://blurha.sh: downscale, convert to linear light,
+// take a handful of DCT coefficients, then pack them into a short
+// base-83 string.
+
+use std::path::Path;
+
+const X_COMPONENTS: u32 = 4;
+const Y_COMPONENTS: u32 = 3;
+const SAMPLE_DIMENSION: u32 = 32;
+
+const BASE83_CHARS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+fn encode_base83(mut value: u32, length: usize) -> String {
+    let mut digits = vec![0u8; length];
+    for i in (0..length).rev() {
+        digits[i] = BASE83_CHARS[(value % 83) as usize];
+        value /= 83;
+    }
+    String::from_utf8(digits).expect("base83 alphabet is ASCII")
+}
+
+fn srgb_to_linear(value: u8) -> f64 {
+    let v = value as f64 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    let byte = if v <= 0.0031308 {
+        v * 12.92 * 255.0 + 0.5
+    } else {
+        (1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5
+    };
+    byte.clamp(0.0, 255.0) as u32
+}
+
+fn sign_pow(value: f64, exp: f64) -> f64 {
+    value.signum() * value.abs().powf(exp)
+}
+
+// DCT-ish basis coefficients for grid position (i, j): the average color
+// weighted by cos(pi*i*x/W)*cos(pi*j*y/H) over every pixel, normalized by
+// pixel count (halved for every basis but the DC term).
+fn basis_factor(pixels: &[[f64; 3]], width: u32, height: u32, i: u32, j: u32) -> [f64; 3] {
+    let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+    let scale = normalisation / (width as f64 * height as f64);
+
+    let mut sum = [0.0f64; 3];
+    for y in 0..height {
+        for x in 0..width {
+            let basis = (std::f64::consts::PI * i as f64 * x as f64 / width as f64).cos()
+                * (std::f64::consts::PI * j as f64 * y as f64 / height as f64).cos();
+            let pixel = pixels[(y * width + x) as usize];
+            sum[0] += basis * pixel[0];
+            sum[1] += basis * pixel[1];
+            sum[2] += basis * pixel[2];
+        }
+    }
+
+    [sum[0] * scale, sum[1] * scale, sum[2] * scale]
+}
+
+fn encode_from_pixels(pixels: &[[f64; 3]], width: u32, height: u32) -> String {
+    let mut factors = Vec::with_capacity((X_COMPONENTS * Y_COMPONENTS) as usize);
+    for j in 0..Y_COMPONENTS {
+        for i in 0..X_COMPONENTS {
+            factors.push(basis_factor(pixels, width, height, i, j));
+        }
+    }
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    let max_ac = ac
+        .iter()
+        .flat_map(|c| c.iter().copied())
+        .fold(0.0f64, |acc, v| acc.max(v.abs()));
+
+    let quantized_max = if ac.is_empty() {
+        0
+    } else {
+        ((max_ac * 166.0 - 0.5).floor() as i32).clamp(0, 82) as u32
+    };
+    let max_value = if ac.is_empty() {
+        1.0
+    } else {
+        (quantized_max + 1) as f64 / 166.0
+    };
+
+    let size_flag = (X_COMPONENTS - 1) + (Y_COMPONENTS - 1) * 9;
+    let dc_value =
+        (linear_to_srgb(dc[0]) << 16) + (linear_to_srgb(dc[1]) << 8) + linear_to_srgb(dc[2]);
+
+    let mut hash = String::new();
+    hash.push_str(&encode_base83(size_flag, 1));
+    hash.push_str(&encode_base83(quantized_max, 1));
+    hash.push_str(&encode_base83(dc_value, 4));
+
+    for component in ac {
+        let quantize = |v: f64| -> u32 {
+            (sign_pow(v / max_value, 0.5) * 9.0 + 9.5).floor().clamp(0.0, 18.0) as u32
+        };
+        let packed = quantize(component[0]) * 19 * 19 + quantize(component[1]) * 19 + quantize(component[2]);
+        hash.push_str(&encode_base83(packed, 2));
+    }
+
+    hash
+}
+
+// Encode a BlurHash for a supported image file, or `None` for anything
+// that isn't an image `image` can decode.
+pub fn encode_file(path: &Path) -> Option<String> {
+    let image = image::open(path).ok()?;
+    let small = image.resize(SAMPLE_DIMENSION, SAMPLE_DIMENSION, image::imageops::FilterType::Triangle);
+    let rgb = small.to_rgb8();
+    let (width, height) = rgb.dimensions();
+
+    let pixels: Vec<[f64; 3]> = rgb
+        .pixels()
+        .map(|p| [srgb_to_linear(p[0]), srgb_to_linear(p[1]), srgb_to_linear(p[2])])
+        .collect();
+
+    Some(encode_from_pixels(&pixels, width, height))
+}
@@ -1,7 +1,16 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod backend;
+mod blurhash;
+mod thumbnails;
+
+use backend::{make_backend, BackendConfig, BackendKind, TagRequest};
+use std::path::{Path, PathBuf};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use thumbnails::ThumbnailCache;
 
 #[derive(Serialize, Deserialize)]
 struct TagResponse {
@@ -22,6 +31,82 @@ struct FolderResponse {
     message: Option<String>,
 }
 
+// Sampling knobs forwarded to the model as a `parameters` object. All
+// fields are optional so callers can override only what they care about
+// and let the backend fall back to its own defaults.
+#[derive(Deserialize)]
+struct GenerationParams {
+    model: Option<String>,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_new_tokens: Option<u32>,
+    do_sample: Option<bool>,
+    stop_tokens: Option<Vec<String>>,
+}
+
+impl GenerationParams {
+    fn to_json(&self) -> serde_json::Value {
+        let mut params = serde_json::Map::new();
+
+        if let Some(model) = &self.model {
+            params.insert("model".to_string(), serde_json::json!(model));
+        }
+        if let Some(temperature) = self.temperature {
+            params.insert("temperature".to_string(), serde_json::json!(temperature));
+        }
+        if let Some(top_p) = self.top_p {
+            params.insert("top_p".to_string(), serde_json::json!(top_p));
+        }
+        if let Some(max_new_tokens) = self.max_new_tokens {
+            params.insert("max_new_tokens".to_string(), serde_json::json!(max_new_tokens));
+        }
+        if let Some(do_sample) = self.do_sample {
+            params.insert("do_sample".to_string(), serde_json::json!(do_sample));
+        }
+        if let Some(stop_tokens) = &self.stop_tokens {
+            params.insert("stop_tokens".to_string(), serde_json::json!(stop_tokens));
+        }
+
+        serde_json::Value::Object(params)
+    }
+}
+
+// One line of the folder job's newline-delimited progress stream
+#[derive(Serialize, Clone)]
+struct FolderProgressEvent {
+    file_path: String,
+    tags: Vec<String>,
+    model_used: Option<String>,
+    status: String,
+    index: usize,
+    total: usize,
+}
+
+fn parse_folder_response(json: &serde_json::Value) -> FolderResponse {
+    let success = json.get("success").and_then(|s| s.as_bool()).unwrap_or(false);
+    let error = json.get("error").and_then(|e| e.as_str()).map(|s| s.to_string());
+    let results = json
+        .get("results")
+        .and_then(|r| r.as_array())
+        .map(|arr| arr.clone())
+        .unwrap_or_default();
+    let summary = json.get("summary").cloned().unwrap_or(serde_json::Value::Null);
+    let folder_path = json
+        .get("folder_path")
+        .and_then(|f| f.as_str())
+        .map(|s| s.to_string());
+    let message = json.get("message").and_then(|m| m.as_str()).map(|s| s.to_string());
+
+    FolderResponse {
+        success,
+        error,
+        results,
+        summary,
+        folder_path,
+        message,
+    }
+}
+
 // Check if Python backend is running
 #[derive(serde::Serialize)]
 struct BackendStatus {
@@ -82,17 +167,113 @@ async fn check_backend_status() -> Result<BackendStatus, String> {
     }
 }
 
+// Resolve the directory the Python backend writes its log files into.
+// This has to be an absolute, OS-appropriate location rather than a
+// "logs" path relative to the process CWD: a bundled app's CWD is
+// whatever the OS launched it from, not the backend's working directory.
+fn log_dir(app_handle: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    app_handle
+        .path_resolver()
+        .app_log_dir()
+        .or_else(|| app_handle.path_resolver().app_data_dir().map(|dir| dir.join("logs")))
+}
+
+// Find the most recently modified log file and return its contents, so a
+// failing run can be attached to a bug report.
+#[tauri::command]
+async fn get_last_log_file(app_handle: tauri::AppHandle) -> Option<String> {
+    let dir = log_dir(&app_handle)?;
+    let entries = std::fs::read_dir(dir).ok()?;
+
+    let newest = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("log"))
+        .max_by_key(|entry| {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+        })?;
+
+    std::fs::read_to_string(newest.path()).ok()
+}
+
+#[derive(Serialize)]
+struct CrashReport {
+    app_version: String,
+    backend_status: BackendStatus,
+    last_log: Option<String>,
+}
+
+// Bundle the last log, current backend status, and app version into a
+// single blob the frontend can attach to a bug report.
+#[tauri::command]
+async fn collect_crash_report(app_handle: tauri::AppHandle) -> Result<CrashReport, String> {
+    let backend_status = check_backend_status().await?;
+    let last_log = get_last_log_file(app_handle.clone()).await;
+    let app_version = app_handle.package_info().version.to_string();
+
+    Ok(CrashReport {
+        app_version,
+        backend_status,
+        last_log,
+    })
+}
+
+// Distinguishes a user-initiated cancellation from a genuine backend
+// failure so the UI can show "canceled" instead of an error toast.
+#[derive(Serialize)]
+#[serde(tag = "status", content = "payload")]
+enum JobOutcome {
+    Completed(FolderResponse),
+    Canceled,
+    Failed(String),
+}
+
+// Shared app state tracking in-flight folder jobs so they can be canceled.
+// The map is behind a plain Mutex (it's mutated rarely, on job start/end);
+// each job's own cancel signal is a bare AtomicBool since that's all a
+// single on/off flag needs.
+#[derive(Default)]
+struct AppState {
+    jobs: std::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<std::sync::atomic::AtomicBool>>>,
+}
+
+// Cancel an in-flight folder job. Returns false if no job with that ID is
+// running (it may have already finished).
+#[tauri::command]
+fn cancel_job(job_id: String, state: tauri::State<AppState>) -> bool {
+    let jobs = state.jobs.lock().unwrap();
+    match jobs.get(&job_id) {
+        Some(flag) => {
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+// Compute a compact BlurHash placeholder for a single file, or `None` if
+// it isn't an image format we can decode. Runs on a blocking thread since
+// decoding and the DCT pass are CPU-bound, not async I/O.
+#[tauri::command]
+async fn get_blurhash(file_path: String) -> Option<String> {
+    tauri::async_runtime::spawn_blocking(move || blurhash::encode_file(std::path::Path::new(&file_path)))
+        .await
+        .unwrap_or(None)
+}
+
 // Get list of supported files in a folder
 #[tauri::command]
 async fn get_folder_files(folder_path: String) -> Result<serde_json::Value, String> {
     let client = reqwest::Client::new();
-    
+
     // Prepare the request payload
     let payload = serde_json::json!({
         "folder_path": folder_path
     });
-    
-    match client
+
+    let mut json = match client
         .post("http://127.0.0.1:5000/api/get-folder-files")
         .json(&payload)
         .timeout(std::time::Duration::from_secs(30))
@@ -101,93 +282,333 @@ async fn get_folder_files(folder_path: String) -> Result<serde_json::Value, Stri
     {
         Ok(response) => {
             if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => Ok(json),
-                    Err(e) => Err(format!("Failed to parse response: {}", e)),
-                }
+                response
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|e| format!("Failed to parse response: {}", e))?
             } else {
-                Err(format!("Backend error: {}", response.status()))
+                return Err(format!("Backend error: {}", response.status()));
+            }
+        }
+        Err(e) => return Err(format!("Request failed: {}", e)),
+    };
+
+    // Attach a BlurHash placeholder to each listed file so the folder grid
+    // can render a blurred gradient before thumbnails load. Each hash is
+    // computed concurrently on a blocking thread since decoding is CPU-bound.
+    if let Some(files) = json.get_mut("files").and_then(|f| f.as_array_mut()) {
+        let paths: Vec<Option<String>> = files
+            .iter()
+            .map(|entry| entry.get("path").and_then(|p| p.as_str()).map(|s| s.to_string()))
+            .collect();
+
+        let hashes = futures_util::future::join_all(paths.into_iter().map(|path| async move {
+            match path {
+                Some(path) => {
+                    tauri::async_runtime::spawn_blocking(move || {
+                        blurhash::encode_file(std::path::Path::new(&path))
+                    })
+                    .await
+                    .unwrap_or(None)
+                }
+                None => None,
+            }
+        }))
+        .await;
+
+        for (entry, hash) in files.iter_mut().zip(hashes) {
+            if entry.is_object() {
+                entry["blurhash"] = hash.map_or(serde_json::Value::Null, serde_json::Value::String);
             }
         }
-        Err(e) => Err(format!("Request failed: {}", e)),
     }
+
+    Ok(json)
 }
 
-// Process folder for tag generation
+// Process folder for tag generation, streaming per-file progress to the
+// frontend instead of blocking on one request for the whole folder. The
+// caller-supplied `job_id` is registered in shared state for the duration
+// of the job so `cancel_job` can flag it from another invocation.
 #[tauri::command]
-async fn process_folder_for_tags(folder_path: String) -> Result<FolderResponse, String> {
+async fn process_folder_for_tags(
+    window: tauri::Window,
+    state: tauri::State<'_, AppState>,
+    job_id: String,
+    folder_path: String,
+    parameters: Option<GenerationParams>,
+    backend_config: Option<BackendConfig>,
+) -> Result<JobOutcome, String> {
+    let cancel_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    state
+        .jobs
+        .lock()
+        .unwrap()
+        .insert(job_id.clone(), cancel_flag.clone());
+
+    let config = backend_config.unwrap_or_default();
+    let outcome = run_folder_job(&window, folder_path, parameters, config, &cancel_flag).await;
+
+    state.jobs.lock().unwrap().remove(&job_id);
+
+    Ok(outcome)
+}
+
+const DIRECT_BACKEND_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+fn list_image_files(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .map(|ext| DIRECT_BACKEND_IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Drives a direct-inference backend (Ollama/OpenAI-compatible/TGI) over a
+// folder by walking its image files and dispatching each one through the
+// `Backend` trait, since those providers have no batch endpoint of their own.
+async fn run_folder_job_per_file(
+    window: &tauri::Window,
+    folder_path: &str,
+    parameters: Option<GenerationParams>,
+    config: BackendConfig,
+    cancel_flag: &std::sync::Arc<std::sync::atomic::AtomicBool>,
+) -> JobOutcome {
+    let files = match list_image_files(Path::new(folder_path)) {
+        Ok(files) => files,
+        Err(e) => return JobOutcome::Failed(format!("Failed to read folder: {}", e)),
+    };
+
+    let backend = make_backend(config.kind);
     let client = reqwest::Client::new();
-    
+    let total = files.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (i, file) in files.iter().enumerate() {
+        if cancel_flag.load(std::sync::atomic::Ordering::SeqCst) {
+            return JobOutcome::Canceled;
+        }
+
+        let file_path = file.to_string_lossy().to_string();
+        let request = TagRequest {
+            file_path: file_path.clone(),
+            context: None,
+            parameters: parameters.as_ref().map(|p| p.to_json()),
+        };
+        let body = backend.build_body(&request);
+
+        let (success, tags, error, model_used) = match client
+            .post(backend.endpoint(&config))
+            .headers(backend::build_headers(&config))
+            .json(&body)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>().await {
+                Ok(json) => {
+                    let result = backend.parse_tags(&json);
+                    (result.success, result.tags, result.error, result.model_used)
+                }
+                Err(e) => (false, Vec::new(), Some(format!("Failed to parse response: {}", e)), None),
+            },
+            Ok(response) => (false, Vec::new(), Some(format!("Backend error: {}", response.status())), None),
+            Err(e) => (false, Vec::new(), Some(format!("Request failed: {}", e)), None),
+        };
+
+        let event = FolderProgressEvent {
+            file_path: file_path.clone(),
+            tags: tags.clone(),
+            model_used: model_used.clone(),
+            status: if success { "done".to_string() } else { "error".to_string() },
+            index: i + 1,
+            total,
+        };
+        let _ = window.emit("folder-progress", &event);
+
+        results.push(serde_json::json!({
+            "file_path": file_path,
+            "success": success,
+            "tags": tags,
+            "error": error,
+            "model_used": model_used,
+        }));
+    }
+
+    JobOutcome::Completed(FolderResponse {
+        success: true,
+        error: None,
+        results,
+        summary: serde_json::json!({ "total": total }),
+        folder_path: Some(folder_path.to_string()),
+        message: None,
+    })
+}
+
+async fn run_folder_job(
+    window: &tauri::Window,
+    folder_path: String,
+    parameters: Option<GenerationParams>,
+    config: BackendConfig,
+    cancel_flag: &std::sync::Arc<std::sync::atomic::AtomicBool>,
+) -> JobOutcome {
+    // The Python shim's `/api/process-folder` is a batch endpoint with its
+    // own NDJSON progress stream; other backends only expose single-file
+    // generation, so drive them through the same `Backend` trait one file
+    // at a time instead.
+    if !matches!(config.kind, BackendKind::PythonShim) {
+        return run_folder_job_per_file(window, &folder_path, parameters, config, cancel_flag).await;
+    }
+
+    let client = reqwest::Client::new();
+
     // Prepare the request payload
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "folder_path": folder_path
     });
-    
-    match client
-        .post("http://127.0.0.1:5000/api/process-folder")
+
+    if let Some(parameters) = parameters {
+        payload["parameters"] = parameters.to_json();
+    }
+
+    // No total timeout here: a folder job can legitimately run far longer
+    // than any fixed deadline. Instead we bound idle time between chunks
+    // below, so a connection that goes quiet (rather than one that's just
+    // slow) is still caught.
+    let response = match client
+        .post(format!("{}/api/process-folder", config.base_url))
+        .headers(backend::build_headers(&config))
         .json(&payload)
-        .timeout(std::time::Duration::from_secs(600)) // 10 minutes for folder processing
         .send()
         .await
     {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => {
-                        let success = json.get("success").and_then(|s| s.as_bool()).unwrap_or(false);
-                        let error = json.get("error")
-                            .and_then(|e| e.as_str())
-                            .map(|s| s.to_string());
-                        let results = json.get("results")
-                            .and_then(|r| r.as_array())
-                            .map(|arr| arr.clone())
-                            .unwrap_or_default();
-                        let summary = json.get("summary").cloned().unwrap_or(serde_json::Value::Null);
-                        let folder_path = json.get("folder_path")
-                            .and_then(|f| f.as_str())
-                            .map(|s| s.to_string());
-                        let message = json.get("message")
-                            .and_then(|m| m.as_str())
-                            .map(|s| s.to_string());
-                        
-                        Ok(FolderResponse {
-                            success,
-                            error,
-                            results,
-                            summary,
-                            folder_path,
-                            message,
-                        })
+        Ok(response) => response,
+        Err(e) => return JobOutcome::Failed(format!("Request failed: {}", e)),
+    };
+
+    if !response.status().is_success() {
+        return JobOutcome::Failed(format!("Backend error: {}", response.status()));
+    }
+
+    const IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+    // The backend writes one JSON object per line: per-file progress
+    // records while it works, then a single terminating FolderResponse.
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut summary: Option<FolderResponse> = None;
+    let mut last_activity = std::time::Instant::now();
+
+    loop {
+        tokio::select! {
+            chunk = stream.next() => {
+                let chunk = match chunk {
+                    Some(Ok(chunk)) => chunk,
+                    Some(Err(e)) => return JobOutcome::Failed(format!("Stream error: {}", e)),
+                    None => break,
+                };
+                last_activity = std::time::Instant::now();
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+                while let Some(newline_pos) = buffer.find('\n') {
+                    let line = buffer[..newline_pos].trim().to_string();
+                    buffer.drain(..=newline_pos);
+
+                    if line.is_empty() {
+                        continue;
+                    }
+
+                    let json: serde_json::Value = match serde_json::from_str(&line) {
+                        Ok(json) => json,
+                        Err(e) => return JobOutcome::Failed(format!("Failed to parse stream record: {}", e)),
+                    };
+
+                    if json.get("index").is_some() && json.get("total").is_some() {
+                        let event = FolderProgressEvent {
+                            file_path: json
+                                .get("file_path")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .to_string(),
+                            tags: json
+                                .get("tags")
+                                .and_then(|t| t.as_array())
+                                .map(|arr| {
+                                    arr.iter()
+                                        .filter_map(|v| v.as_str())
+                                        .map(|s| s.to_string())
+                                        .collect()
+                                })
+                                .unwrap_or_default(),
+                            model_used: json
+                                .get("model_used")
+                                .and_then(|m| m.as_str())
+                                .map(|s| s.to_string()),
+                            status: json
+                                .get("status")
+                                .and_then(|s| s.as_str())
+                                .unwrap_or("unknown")
+                                .to_string(),
+                            index: json.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize,
+                            total: json.get("total").and_then(|t| t.as_u64()).unwrap_or(0) as usize,
+                        };
+
+                        let _ = window.emit("folder-progress", &event);
+                    } else {
+                        summary = Some(parse_folder_response(&json));
                     }
-                    Err(e) => Err(format!("Failed to parse response: {}", e)),
                 }
-            } else {
-                Err(format!("Backend error: {}", response.status()))
+            }
+            _ = tokio::time::sleep(std::time::Duration::from_millis(200)) => {
+                if cancel_flag.load(std::sync::atomic::Ordering::SeqCst) {
+                    // Dropping `stream`/`response` here aborts the connection.
+                    return JobOutcome::Canceled;
+                }
+                if last_activity.elapsed() > IDLE_TIMEOUT {
+                    return JobOutcome::Failed(format!(
+                        "Backend went quiet for over {}s",
+                        IDLE_TIMEOUT.as_secs()
+                    ));
+                }
             }
         }
-        Err(e) => Err(format!("Request failed: {}", e)),
+    }
+
+    match summary {
+        Some(summary) => JobOutcome::Completed(summary),
+        None => JobOutcome::Failed("Backend closed the connection without a final summary".to_string()),
     }
 }
 
 // Process file for tag generation
 #[tauri::command]
-async fn process_file_for_tags(file_path: String, context: Option<String>) -> Result<TagResponse, String> {
+async fn process_file_for_tags(
+    file_path: String,
+    context: Option<String>,
+    parameters: Option<GenerationParams>,
+    backend_config: Option<BackendConfig>,
+) -> Result<TagResponse, String> {
+    let config = backend_config.unwrap_or_default();
+    let backend = make_backend(config.kind);
     let client = reqwest::Client::new();
-    
-    // Prepare the request payload
-    let mut payload = serde_json::json!({
-        "file_path": file_path
-    });
-    
-    // Add context if provided
-    if let Some(ctx) = context {
-        if !ctx.trim().is_empty() {
-            payload["context"] = serde_json::Value::String(ctx);
-        }
-    }
-    
+
+    let request = TagRequest {
+        file_path,
+        context,
+        parameters: parameters.map(|p| p.to_json()),
+    };
+    let payload = backend.build_body(&request);
+
     match client
-        .post("http://127.0.0.1:5000/api/process-file")
+        .post(backend.endpoint(&config))
+        .headers(backend::build_headers(&config))
         .json(&payload)
         .timeout(std::time::Duration::from_secs(360)) // 6 minutes timeout for vision model processing
         .send()
@@ -197,32 +618,14 @@ async fn process_file_for_tags(file_path: String, context: Option<String>) -> Re
             if response.status().is_success() {
                 match response.json::<serde_json::Value>().await {
                     Ok(json) => {
-                        let success = json.get("success").and_then(|s| s.as_bool()).unwrap_or(false);
-                        let tags = json.get("tags")
-                            .and_then(|t| t.as_array())
-                            .map(|arr| {
-                                arr.iter()
-                                    .filter_map(|v| v.as_str())
-                                    .map(|s| s.to_string())
-                                    .collect()
-                            })
-                            .unwrap_or_default();
-                        let error = json.get("error")
-                            .and_then(|e| e.as_str())
-                            .map(|s| s.to_string());
-                        let file_type = json.get("file_type")
-                            .and_then(|f| f.as_str())
-                            .map(|s| s.to_string());
-                        let model_used = json.get("model_used")
-                            .and_then(|m| m.as_str())
-                            .map(|s| s.to_string());
-                        
+                        let result = backend.parse_tags(&json);
+
                         Ok(TagResponse {
-                            success,
-                            tags,
-                            error,
-                            file_type,
-                            model_used,
+                            success: result.success,
+                            tags: result.tags,
+                            error: result.error,
+                            file_type: result.file_type,
+                            model_used: result.model_used,
                         })
                     }
                     Err(e) => Err(format!("Failed to parse response: {}", e)),
@@ -237,11 +640,20 @@ async fn process_file_for_tags(file_path: String, context: Option<String>) -> Re
 
 fn main() {
     tauri::Builder::default()
+        .manage(AppState::default())
+        .manage(ThumbnailCache::default())
+        .register_uri_scheme_protocol("tagsense", |app, request| {
+            thumbnails::handle_request(&app.state::<ThumbnailCache>(), request)
+        })
         .invoke_handler(tauri::generate_handler![
             check_backend_status,
             process_file_for_tags,
             process_folder_for_tags,
-            get_folder_files
+            cancel_job,
+            get_folder_files,
+            get_blurhash,
+            get_last_log_file,
+            collect_crash_report
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,181 @@
+// Serves downscaled previews through a custom `tagsense://thumb/<path>`
+// asset protocol, with `Range` support so large previews can be fetched
+// in slices instead of loading the whole file at once. Only formats
+// `image` can decode are supported today (video thumbnailing needs a
+// frame-extraction path we don't have yet, so video requests 404).
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const THUMB_MAX_DIMENSION: u32 = 256;
+
+// Caps the cache at a fixed entry count rather than a byte budget, since
+// thumbnail bytes are small and bounded by THUMB_MAX_DIMENSION; this keeps
+// eviction O(1) bookkeeping without having to track sizes.
+const MAX_CACHE_ENTRIES: usize = 512;
+
+// Thumbnails keyed by source path, invalidated whenever the source file's
+// mtime changes. Bounded to MAX_CACHE_ENTRIES, evicting least-recently-used
+// so browsing large folders doesn't grow the cache without limit.
+#[derive(Default)]
+pub struct ThumbnailCache {
+    entries: Mutex<HashMap<PathBuf, (SystemTime, Vec<u8>)>>,
+    // Tracks access order, oldest first, so we know what to evict.
+    recency: Mutex<VecDeque<PathBuf>>,
+}
+
+impl ThumbnailCache {
+    pub fn get_or_generate(&self, source: &Path) -> Result<Vec<u8>, String> {
+        let mtime = std::fs::metadata(source)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("failed to stat {}: {}", source.display(), e))?;
+
+        {
+            let entries = self.entries.lock().unwrap();
+            if let Some((cached_mtime, bytes)) = entries.get(source) {
+                if *cached_mtime == mtime {
+                    let bytes = bytes.clone();
+                    drop(entries);
+                    self.touch(source);
+                    return Ok(bytes);
+                }
+            }
+        }
+
+        let bytes = generate_thumbnail(source)?;
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(source.to_path_buf(), (mtime, bytes.clone()));
+        self.touch(source);
+        self.evict_if_over_capacity();
+        Ok(bytes)
+    }
+
+    // Moves `source` to the back of the recency queue (most-recently-used).
+    fn touch(&self, source: &Path) {
+        let mut recency = self.recency.lock().unwrap();
+        recency.retain(|p| p != source);
+        recency.push_back(source.to_path_buf());
+    }
+
+    fn evict_if_over_capacity(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        let mut recency = self.recency.lock().unwrap();
+        while entries.len() > MAX_CACHE_ENTRIES {
+            let Some(oldest) = recency.pop_front() else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+    }
+}
+
+fn generate_thumbnail(source: &Path) -> Result<Vec<u8>, String> {
+    let image =
+        image::open(source).map_err(|e| format!("failed to decode {}: {}", source.display(), e))?;
+    let thumbnail = image.thumbnail(THUMB_MAX_DIMENSION, THUMB_MAX_DIMENSION);
+
+    let mut bytes = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Jpeg(80))
+        .map_err(|e| format!("failed to encode thumbnail: {}", e))?;
+
+    Ok(bytes)
+}
+
+// Parses a `Range: bytes=start-end` header into an inclusive byte range.
+// Also handles the suffix form `bytes=-N` (RFC 7233 section 2.1: "the
+// final N bytes"). Returns `Err` for a malformed range or one that falls outside
+// `len`, so the caller can answer with 416 instead of panicking.
+fn parse_range(header: &str, len: u64) -> Result<(u64, u64), ()> {
+    if len == 0 {
+        return Err(());
+    }
+
+    let spec = header.strip_prefix("bytes=").ok_or(())?;
+    let (start_str, end_str) = spec.split_once('-').ok_or(())?;
+
+    let (start, end) = if start_str.is_empty() {
+        let suffix_len: u64 = end_str.parse().map_err(|_| ())?;
+        if suffix_len == 0 {
+            return Err(());
+        }
+        let suffix_len = suffix_len.min(len);
+        (len - suffix_len, len - 1)
+    } else {
+        let start: u64 = start_str.parse().map_err(|_| ())?;
+        let end: u64 = if end_str.is_empty() {
+            len - 1
+        } else {
+            end_str.parse().map_err(|_| ())?
+        };
+        (start, end)
+    };
+
+    if start > end || end >= len {
+        return Err(());
+    }
+
+    Ok((start, end))
+}
+
+fn source_path_from_uri(uri: &str) -> PathBuf {
+    let encoded = uri.strip_prefix("tagsense://thumb/").unwrap_or("");
+    let decoded = percent_encoding::percent_decode_str(encoded).decode_utf8_lossy();
+    PathBuf::from(decoded.into_owned())
+}
+
+pub fn handle_request(
+    cache: &ThumbnailCache,
+    request: &tauri::http::Request,
+) -> Result<tauri::http::Response, Box<dyn std::error::Error>> {
+    let source = source_path_from_uri(request.uri());
+
+    let bytes = match cache.get_or_generate(&source) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return tauri::http::ResponseBuilder::new()
+                .status(404)
+                .body(e.into_bytes())
+                .map_err(Into::into);
+        }
+    };
+    let total_len = bytes.len() as u64;
+
+    let range_header = request
+        .headers()
+        .get("range")
+        .and_then(|v| v.to_str().ok());
+
+    match range_header {
+        Some(range) => match parse_range(range, total_len) {
+            Ok((start, end)) => {
+                let slice = bytes[start as usize..=end as usize].to_vec();
+                tauri::http::ResponseBuilder::new()
+                    .status(206)
+                    .header("Content-Type", "image/jpeg")
+                    .header("Accept-Ranges", "bytes")
+                    .header("Content-Range", format!("bytes {}-{}/{}", start, end, total_len))
+                    .header("Content-Length", slice.len().to_string())
+                    .body(slice)
+                    .map_err(Into::into)
+            }
+            Err(()) => tauri::http::ResponseBuilder::new()
+                .status(416)
+                .header("Content-Range", format!("bytes */{}", total_len))
+                .body(Vec::new())
+                .map_err(Into::into),
+        },
+        None => tauri::http::ResponseBuilder::new()
+            .status(200)
+            .header("Content-Type", "image/jpeg")
+            .header("Accept-Ranges", "bytes")
+            .header("Content-Length", total_len.to_string())
+            .body(bytes)
+            .map_err(Into::into),
+    }
+}